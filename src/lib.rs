@@ -38,88 +38,315 @@ use nom::combinator::opt;
 use nom::sequence::tuple;
 use nom::IResult;
 
-use url::percent_encoding::percent_decode;
 use url::Url;
 
+use std::fmt;
 use std::fs;
 use std::path::Path;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+/// The error type returned when parsing or fetching a robots.txt fails.
+#[derive(Debug)]
+pub enum RobotsError {
+    /// The input could not be parsed as a robots.txt file.
+    Parse {
+        /// The byte offset into the input at which parsing broke.
+        offset: usize,
+        /// The 1-indexed line number corresponding to `offset`.
+        line: usize,
+    },
+    /// Reading the robots.txt file from disk failed.
+    Io(std::io::Error),
+    /// Fetching the robots.txt file over the network failed.
+    #[cfg(feature = "web")]
+    Http(reqwest::Error),
+}
+
+impl fmt::Display for RobotsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RobotsError::Parse { offset, line } => write!(
+                f,
+                "could not parse robots.txt at byte {} (line {})",
+                offset, line
+            ),
+            RobotsError::Io(err) => write!(f, "could not read robots.txt: {}", err),
+            #[cfg(feature = "web")]
+            RobotsError::Http(err) => write!(f, "could not fetch robots.txt: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RobotsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RobotsError::Parse { .. } => None,
+            RobotsError::Io(err) => Some(err),
+            #[cfg(feature = "web")]
+            RobotsError::Http(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for RobotsError {
+    fn from(err: std::io::Error) -> Self {
+        RobotsError::Io(err)
+    }
+}
+
+#[cfg(feature = "web")]
+impl From<reqwest::Error> for RobotsError {
+    fn from(err: reqwest::Error) -> Self {
+        RobotsError::Http(err)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct RobotsParser {
     rules: Vec<Robots>,
+    /// When this result was fetched from the network, if at all.
+    #[cfg(feature = "web")]
+    fetched_at: Option<std::time::SystemTime>,
+    /// When this result should be considered stale, derived from the
+    /// response's `Expires`/`Cache-Control: max-age` headers, if any.
+    #[cfg(feature = "web")]
+    expires_at: Option<std::time::SystemTime>,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Robots {
     UserAgent(String, Vec<Rule>),
     GlobalRule(Rule),
+    /// A group-independent `Sitemap:` directive.
+    Sitemap(Url),
 }
 
 impl Robots {
+    // Same matching rule `can_fetch` uses: an exact (case-insensitive) match,
+    // or the `*` wildcard group.
+    fn agent_matches(own_agent: &str, agent: &str) -> bool {
+        let cleaned_user_agent = agent.split('/').nth(0).unwrap_or("");
+        own_agent == "*" || own_agent.to_lowercase() == cleaned_user_agent.to_lowercase()
+    }
+
     fn is_applicable(&self, agent: &str, path: &str) -> bool {
         match self {
-            Robots::UserAgent(s, _) => {
-                let cleaned_user_agent = agent.split('/').nth(0).unwrap_or("");
-                if s == "*" || *s == cleaned_user_agent.to_lowercase() {
-                    true
-                } else {
-                    false
-                }
-            }
+            Robots::UserAgent(s, _) => Robots::agent_matches(s, agent),
             Robots::GlobalRule(rule) => rule.is_applicable(path),
+            Robots::Sitemap(_) => false,
         }
     }
 
     // Precondition: Applicability has been proven
+    //
+    // Among all rules that match `path`, the longest matching pattern wins
+    // (ties go to `Allow`), per the de-facto robots.txt standard. A path with
+    // no matching rule at all is allowed by default.
     fn is_allowed(&self, path: &str) -> bool {
         match self {
             Robots::UserAgent(_, rules) => {
+                let mut best: Option<(usize, bool)> = None;
                 for rule in rules {
-                    if rule.is_applicable(path) {
-                        return rule.allowed();
+                    if let Some(len) = rule.match_len(path) {
+                        let allow = rule.allowed();
+                        let replace = match best {
+                            Some((best_len, best_allow)) => {
+                                len > best_len || (len == best_len && allow && !best_allow)
+                            }
+                            None => true,
+                        };
+                        if replace {
+                            best = Some((len, allow));
+                        }
                     }
                 }
+                best.map(|(_, allow)| allow).unwrap_or(true)
             }
-            Robots::GlobalRule(rule) => return rule.allowed(),
+            Robots::GlobalRule(rule) => rule.allowed(),
+            Robots::Sitemap(_) => true,
         }
-        false
     }
 }
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Rule {
     Allow(String),
     Disallow(String),
+    /// `Crawl-delay:` — the number of seconds a crawler should wait between
+    /// successive requests.
+    CrawlDelay(f64),
+    /// `Request-rate:` — at most `requests` requests per `seconds` seconds.
+    RequestRate(RequestRate),
+    /// `Visit-time:` — the `(hour, minute)` GMT window crawling is allowed in.
+    VisitTime(VisitTime),
     Extension,
 }
 
+/// The `requests`/`seconds` pair declared by a `Request-rate:` directive.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct RequestRate {
+    pub requests: u32,
+    pub seconds: u32,
+}
+
+/// The `(hour, minute)` GMT window declared by a `Visit-time:` directive.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct VisitTime {
+    pub from: (u8, u8),
+    pub to: (u8, u8),
+}
+
 impl Rule {
-    fn is_applicable(&self, path: &str) -> bool {
-        let own_path = match self {
+    fn own_path(&self) -> &str {
+        match self {
             Rule::Allow(s) | Rule::Disallow(s) => s,
             _ => "",
-        };
+        }
+    }
 
-        own_path == "*" || path.starts_with(own_path)
+    fn is_applicable(&self, path: &str) -> bool {
+        self.match_len(path).is_some()
+    }
+
+    // Returns the number of path characters consumed by this rule's pattern,
+    // or `None` if the pattern does not match `path` at all (or if this rule
+    // is not a path rule at all). The length is used to pick the most
+    // specific rule when several apply.
+    fn match_len(&self, path: &str) -> Option<usize> {
+        match self {
+            Rule::Allow(_) | Rule::Disallow(_) => match_pattern(self.own_path(), path),
+            _ => None,
+        }
     }
 
     // Precondition: Applicability has been proven
     fn allowed(&self) -> bool {
-        match self {
-            Rule::Allow(_) => true,
-            _ => false,
+        matches!(self, Rule::Allow(_))
+    }
+}
+
+// Reserved path/query characters (RFC 3986 §2.2): a literal occurrence is
+// left as-is, but a percent-encoded occurrence of the same byte must stay
+// percent-encoded, so e.g. a literal `&` query separator is never confused
+// with a `%26` that denotes a literal `&` inside a value.
+const PATH_RESERVED: &[u8] = b":/?#[]@!$&'()*+,;=%";
+
+// Normalizes a piece of a URL path (or rule pattern literal) to one
+// canonical percent-encoding: a `%XX` escape is decoded only when it denotes
+// an unreserved byte; every reserved byte keeps whichever form (literal or
+// percent-encoded) it already had. This makes e.g. `/%7Emak/` and `/~mak/`
+// compare equal, while `%26` and a literal `&` stay distinct.
+fn normalize_path(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(decoded) = decode_percent_triplet(&bytes[i..]) {
+            if decoded.is_ascii_alphanumeric() || b"-_.~".contains(&decoded) {
+                out.push(decoded as char);
+            } else {
+                out.push_str(&format!("%{:02X}", decoded));
+            }
+            i += 3;
+        } else {
+            let byte = bytes[i];
+            if byte.is_ascii_alphanumeric()
+                || b"-_.~".contains(&byte)
+                || PATH_RESERVED.contains(&byte)
+            {
+                out.push(byte as char);
+            } else {
+                out.push_str(&format!("%{:02X}", byte));
+            }
+            i += 1;
         }
     }
+    out
+}
+
+// Decodes a leading `%XX` escape, if `bytes` starts with one.
+fn decode_percent_triplet(bytes: &[u8]) -> Option<u8> {
+    if bytes.len() < 3 || bytes[0] != b'%' {
+        return None;
+    }
+    let hi = (bytes[1] as char).to_digit(16)?;
+    let lo = (bytes[2] as char).to_digit(16)?;
+    Some((hi * 16 + lo) as u8)
 }
+
+// Matches `path` against a robots.txt pattern that may contain `*` wildcards
+// (matching any sequence of characters, including none) and a trailing `$`
+// anchor (matching only the end of the path). Both sides are normalized to
+// one percent-encoding before comparison. Returns the number of (normalized)
+// path characters consumed by the match, so the longest-match rule can pick
+// the most specific pattern.
+fn match_pattern(pattern: &str, path: &str) -> Option<usize> {
+    let anchored = pattern.ends_with('$');
+    let body = if anchored {
+        &pattern[..pattern.len() - 1]
+    } else {
+        pattern
+    };
+    // A wildcard immediately before the `$` consumes the rest of the path by
+    // itself, so the match always runs through the end of the path.
+    let trailing_wildcard = anchored && body.ends_with('*');
+    let anchored = anchored && !trailing_wildcard;
+    let path = normalize_path(path);
+
+    let segments: Vec<String> = body.split('*').map(normalize_path).collect();
+    let first = &segments[0];
+    if !path.starts_with(first.as_str()) {
+        return None;
+    }
+
+    let mut pos = first.len();
+    let last = segments.len() - 1;
+    for (index, segment) in segments.iter().enumerate().skip(1) {
+        if segment.is_empty() {
+            continue;
+        }
+        if anchored && index == last {
+            // The final literal of an anchored pattern must align with the
+            // end of the path, not merely occur somewhere after `pos`.
+            let start = match path.len().checked_sub(segment.len()) {
+                Some(start) if start >= pos => start,
+                _ => return None,
+            };
+            if &path[start..] != segment.as_str() {
+                return None;
+            }
+            pos = path.len();
+        } else {
+            match path[pos..].find(segment.as_str()) {
+                Some(offset) => pos += offset + segment.len(),
+                None => return None,
+            }
+        }
+    }
+
+    if trailing_wildcard {
+        pos = path.len();
+    } else if anchored && pos != path.len() {
+        return None;
+    }
+    Some(pos)
+}
+
 impl RobotsParser {
 
     /// Creates a new `RobotsParser` from the given `Robots` Rules
     pub fn new(rules: Vec<Robots>) -> RobotsParser {
-        RobotsParser { rules }
+        RobotsParser {
+            rules,
+            #[cfg(feature = "web")]
+            fetched_at: None,
+            #[cfg(feature = "web")]
+            expires_at: None,
+        }
     }
 
     /// Parses a robots.txt input string
-    pub fn parse<'a>(input: &'a str) -> Result<RobotsParser, &'static str> {
+    pub fn parse<'a>(input: &'a str) -> Result<RobotsParser, RobotsError> {
         let mut rules = vec![];
+        let full_input = input;
         let mut input = input;
 
         //Always add a Allow(/robots.txt) at the start
@@ -130,6 +357,7 @@ impl RobotsParser {
                 RobotsParser::comment_line_parser(),
                 map_opt(RobotsParser::crlf_parse(), |_| Some(None::<Robots>)),
                 RobotsParser::parse_user_agent(),
+                RobotsParser::parse_sitemap(),
                 map_opt(RobotsParser::parse_rule(), |rule| {
                     Some(Some(Robots::GlobalRule(rule)))
                 }),
@@ -141,7 +369,9 @@ impl RobotsParser {
                 }
                 Ok((input, None)) => input,
                 Err(_) => {
-                    return Err("Could not parse Robots.txt");
+                    let offset = full_input.len() - input.len();
+                    let line = full_input[..offset].matches('\n').count() + 1;
+                    return Err(RobotsError::Parse { offset, line });
                 }
             };
 
@@ -151,20 +381,79 @@ impl RobotsParser {
             }
         }
 
-        Ok(RobotsParser { rules: rules })
+        Ok(RobotsParser::new(rules))
     }
 
     /// Parses a robots.txt file from the given path
-    pub fn parse_path<P: AsRef<Path>>(path: P) -> Result<RobotsParser, &'static str> {
-        let data = fs::read_to_string(path).expect("Unable to read file");
+    pub fn parse_path<P: AsRef<Path>>(path: P) -> Result<RobotsParser, RobotsError> {
+        let data = fs::read_to_string(path)?;
         RobotsParser::parse(&data)
     }
 
-    /// Parses a robots.txt file from the given url
+    /// Fetches and parses a robots.txt file from the given url.
+    ///
+    /// Follows the reference behavior for failed fetches instead of
+    /// panicking: a 4xx response (unauthorized/not-found) is treated as "no
+    /// rules", allowing everything, while a 5xx response is treated
+    /// conservatively as disallowing everything. The fetch time and any
+    /// `Expires`/`Cache-Control: max-age` expiry are recorded so callers can
+    /// check [`RobotsParser::is_expired`] before reusing the result.
     #[cfg(feature = "web")]
-    pub fn parse_url<U: Into<Url>>(url: U) -> Result<RobotsParser, &'static str> {
-        let data = reqwest::get(url.into()).expect("Unable to read file from url").text().expect("Unable to rad file from url");
-        RobotsParser::parse(&data)
+    pub fn parse_url<U: Into<Url>>(url: U) -> Result<RobotsParser, RobotsError> {
+        let mut response = reqwest::get(url.into())?;
+        let status = response.status();
+        let expires_at = RobotsParser::expiry_from_headers(response.headers());
+
+        let mut parser = if status.is_client_error() {
+            RobotsParser::new(vec![Robots::GlobalRule(Rule::Allow("*".to_owned()))])
+        } else if status.is_server_error() {
+            RobotsParser::new(vec![Robots::GlobalRule(Rule::Disallow("*".to_owned()))])
+        } else {
+            RobotsParser::parse(&response.text()?)?
+        };
+
+        parser.fetched_at = Some(std::time::SystemTime::now());
+        parser.expires_at = expires_at;
+        Ok(parser)
+    }
+
+    /// Returns when this result was fetched from the network, if it was
+    /// obtained via [`RobotsParser::parse_url`].
+    #[cfg(feature = "web")]
+    pub fn fetched_at(&self) -> Option<std::time::SystemTime> {
+        self.fetched_at
+    }
+
+    /// Returns whether this result is past the expiry advertised by the
+    /// server's caching headers at fetch time. Always `false` for results
+    /// that weren't fetched via [`RobotsParser::parse_url`], or whose
+    /// response carried no caching headers.
+    #[cfg(feature = "web")]
+    pub fn is_expired(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => std::time::SystemTime::now() >= expires_at,
+            None => false,
+        }
+    }
+
+    #[cfg(feature = "web")]
+    fn expiry_from_headers(headers: &reqwest::header::HeaderMap) -> Option<std::time::SystemTime> {
+        if let Some(max_age) = headers
+            .get(reqwest::header::CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value.split(',').find_map(|directive| {
+                    directive.trim().strip_prefix("max-age=")?.parse::<u64>().ok()
+                })
+            })
+        {
+            return Some(std::time::SystemTime::now() + std::time::Duration::from_secs(max_age));
+        }
+
+        headers
+            .get(reqwest::header::EXPIRES)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_http_date)
     }
 
     /// Parses a space
@@ -225,13 +514,32 @@ impl RobotsParser {
         }
     }
 
+    /// Parses a `Sitemap:` directive, e.g. `Sitemap: https://example.com/sitemap.xml`
+    ///
+    /// Unparseable URLs are skipped (the line is still consumed) rather than
+    /// failing the whole file, since a single malformed sitemap shouldn't
+    /// stop the rest of robots.txt from being read.
+    fn parse_sitemap() -> impl Fn(&str) -> IResult<&str, Option<Robots>> {
+        move |input: &str| {
+            let (input, _) = tag_no_case("sitemap:")(input)?;
+            let (input, _) = RobotsParser::space_parser()(input)?;
+            let (input, url) = RobotsParser::parse_file_path(input)?;
+
+            // Parses optional comment after the url
+            let (input, _) = opt(RobotsParser::comment_parser())(input).unwrap_or((input, None));
+            let (input, _) = cond(!input.is_empty(), RobotsParser::crlf_parse())(input)?;
+
+            Ok((input, Url::parse(&url).ok().map(Robots::Sitemap)))
+        }
+    }
+
     /// Parses as many rules it can find
     fn parse_rules<'a>() -> impl Fn(&'a str) -> IResult<&'a str, Vec<Rule>> {
         move |input: &'a str| {
             let mut rules = vec![];
             let mut input = input;
             loop {
-                input = match RobotsParser::parse_rule()(input) {
+                input = match RobotsParser::parse_any_rule()(input) {
                     Ok((input, rule)) => {
                         rules.push(rule);
                         input
@@ -245,9 +553,21 @@ impl RobotsParser {
         }
     }
 
-    /// Parses exactly one rule
-    fn parse_rule<'a>() -> impl Fn(&'a str) -> IResult<&'a str, Rule> {
-        move |input: &'a str| {
+    /// Parses exactly one directive allowed inside a user-agent group: an
+    /// `Allow`/`Disallow` rule, or a `Crawl-delay`/`Request-rate`/`Visit-time`
+    /// politeness directive.
+    fn parse_any_rule() -> impl Fn(&str) -> IResult<&str, Rule> {
+        alt((
+            RobotsParser::parse_rule(),
+            RobotsParser::parse_crawl_delay(),
+            RobotsParser::parse_request_rate(),
+            RobotsParser::parse_visit_time(),
+        ))
+    }
+
+    /// Parses exactly one `Allow`/`Disallow` rule
+    fn parse_rule() -> impl Fn(&str) -> IResult<&str, Rule> {
+        move |input: &str| {
             let (input, allowence) = alt((tag("Allow:"), tag("Disallow:")))(input)?;
             let (input, _) = RobotsParser::space_parser()(input)?;
             let (input, path) = RobotsParser::parse_file_path(input)?;
@@ -256,7 +576,7 @@ impl RobotsParser {
             let (input, _) = opt(RobotsParser::comment_parser())(input).unwrap_or((input, None));
 
             // CRLF is optional, when the file is empty
-            let (input, _) = cond(input.len() != 0, RobotsParser::crlf_parse())(input)?;
+            let (input, _) = cond(!input.is_empty(), RobotsParser::crlf_parse())(input)?;
 
             // Empty Disallow means allow all
             if allowence == "Disallow:" && path.is_empty() {
@@ -271,6 +591,63 @@ impl RobotsParser {
         }
     }
 
+    /// Parses a `Crawl-delay:` directive, e.g. `Crawl-delay: 1.5`
+    fn parse_crawl_delay() -> impl Fn(&str) -> IResult<&str, Rule> {
+        move |input: &str| {
+            let (input, _) = tag_no_case("crawl-delay:")(input)?;
+            let (input, _) = RobotsParser::space_parser()(input)?;
+            let (input, value) = take_while1(|c: char| c.is_ascii_digit() || c == '.')(input)?;
+            let (input, _) = opt(RobotsParser::comment_parser())(input).unwrap_or((input, None));
+            let (input, _) = cond(!input.is_empty(), RobotsParser::crlf_parse())(input)?;
+
+            Ok((input, Rule::CrawlDelay(value.parse().unwrap_or(0.0))))
+        }
+    }
+
+    /// Parses a `Request-rate:` directive, e.g. `Request-rate: 1/10`
+    /// (one request per 10 seconds)
+    fn parse_request_rate() -> impl Fn(&str) -> IResult<&str, Rule> {
+        move |input: &str| {
+            let (input, _) = tag_no_case("request-rate:")(input)?;
+            let (input, _) = RobotsParser::space_parser()(input)?;
+            let (input, requests) = take_while1(|c: char| c.is_ascii_digit())(input)?;
+            let (input, _) = tag("/")(input)?;
+            let (input, seconds) = take_while1(|c: char| c.is_ascii_digit())(input)?;
+            let (input, _) = opt(RobotsParser::comment_parser())(input).unwrap_or((input, None));
+            let (input, _) = cond(!input.is_empty(), RobotsParser::crlf_parse())(input)?;
+
+            Ok((
+                input,
+                Rule::RequestRate(RequestRate {
+                    requests: requests.parse().unwrap_or(0),
+                    seconds: seconds.parse().unwrap_or(0),
+                }),
+            ))
+        }
+    }
+
+    /// Parses a `Visit-time:` directive, e.g. `Visit-time: 0600-0845`
+    /// (hours and minutes, GMT)
+    fn parse_visit_time() -> impl Fn(&str) -> IResult<&str, Rule> {
+        move |input: &str| {
+            let (input, _) = tag_no_case("visit-time:")(input)?;
+            let (input, _) = RobotsParser::space_parser()(input)?;
+            let (input, from) = take_while1(|c: char| c.is_ascii_digit())(input)?;
+            let (input, _) = tag("-")(input)?;
+            let (input, to) = take_while1(|c: char| c.is_ascii_digit())(input)?;
+            let (input, _) = opt(RobotsParser::comment_parser())(input).unwrap_or((input, None));
+            let (input, _) = cond(!input.is_empty(), RobotsParser::crlf_parse())(input)?;
+
+            Ok((
+                input,
+                Rule::VisitTime(VisitTime {
+                    from: parse_hhmm(from),
+                    to: parse_hhmm(to),
+                }),
+            ))
+        }
+    }
+
     /// Parses a path as specified
     /// Paths do not include `#` as they indicate a comment
     fn parse_file_path<'a>(input: &'a str) -> IResult<&'a str, String> {
@@ -283,17 +660,129 @@ impl RobotsParser {
         let url = Url::parse(path);
         match url {
             Ok(url) => {
-                let path = percent_decode(url.path().as_bytes()).decode_utf8().unwrap();
+                let mut path = url.path().to_owned();
+                if let Some(query) = url.query() {
+                    path.push('?');
+                    path.push_str(query);
+                }
                 for rule in &*self.rules {
                     if rule.is_applicable(agent, &path) {
                         return rule.is_allowed(&path);
                     }
                 }
-                false
+                // No applicable group at all: the standard default is to allow.
+                true
             }
             Err(_) => return false,
         }
     }
+
+    /// Returns the `Crawl-delay` (in seconds) declared for the given agent, if any.
+    pub fn crawl_delay(&self, agent: &str) -> Option<f64> {
+        self.rule_value(agent, |rule| match rule {
+            Rule::CrawlDelay(delay) => Some(*delay),
+            _ => None,
+        })
+    }
+
+    /// Returns the `Request-rate` declared for the given agent, if any.
+    pub fn request_rate(&self, agent: &str) -> Option<RequestRate> {
+        self.rule_value(agent, |rule| match rule {
+            Rule::RequestRate(rate) => Some(*rate),
+            _ => None,
+        })
+    }
+
+    /// Returns the `Visit-time` window declared for the given agent, if any.
+    pub fn visit_time(&self, agent: &str) -> Option<VisitTime> {
+        self.rule_value(agent, |rule| match rule {
+            Rule::VisitTime(visit_time) => Some(*visit_time),
+            _ => None,
+        })
+    }
+
+    /// Returns all `Sitemap:` URLs declared in the robots.txt file.
+    pub fn sitemaps(&self) -> Vec<&Url> {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                Robots::Sitemap(url) => Some(url),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Resolves the first `Robots::UserAgent` group applicable to `agent` (the
+    // same matching logic `can_fetch` uses) and extracts the first rule
+    // `extract` recognizes from it.
+    fn rule_value<T>(&self, agent: &str, extract: impl Fn(&Rule) -> Option<T>) -> Option<T> {
+        for group in &self.rules {
+            if let Robots::UserAgent(name, rules) = group {
+                if Robots::agent_matches(name, agent) {
+                    return rules.iter().find_map(|rule| extract(rule));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn parse_hhmm(value: &str) -> (u8, u8) {
+    let hours = value.get(0..2).and_then(|s| s.parse().ok()).unwrap_or(0);
+    let minutes = value.get(2..4).and_then(|s| s.parse().ok()).unwrap_or(0);
+    (hours, minutes)
+}
+
+// Parses an RFC 1123 HTTP-date, e.g. "Wed, 21 Oct 2026 07:28:00 GMT", as seen
+// in `Expires` headers. Returns `None` for any other format rather than
+// failing the whole fetch over an unparseable header.
+#[cfg(feature = "web")]
+fn parse_http_date(value: &str) -> Option<std::time::SystemTime> {
+    let parts: Vec<&str> = value.trim().split_whitespace().collect();
+    if parts.len() != 6 {
+        return None;
+    }
+
+    let day: u64 = parts[1].parse().ok()?;
+    let month = match parts[2] {
+        "Jan" => 1,
+        "Feb" => 2,
+        "Mar" => 3,
+        "Apr" => 4,
+        "May" => 5,
+        "Jun" => 6,
+        "Jul" => 7,
+        "Aug" => 8,
+        "Sep" => 9,
+        "Oct" => 10,
+        "Nov" => 11,
+        "Dec" => 12,
+        _ => return None,
+    };
+    let year: u64 = parts[3].parse().ok()?;
+
+    let mut time = parts[4].split(':');
+    let hour: u64 = time.next()?.parse().ok()?;
+    let minute: u64 = time.next()?.parse().ok()?;
+    let second: u64 = time.next()?.parse().ok()?;
+
+    let is_leap = |y: u64| (y % 4 == 0 && y % 100 != 0) || y % 400 == 0;
+    let days_in_month = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+
+    let mut days: u64 = 0;
+    for y in 1970..year {
+        days += if is_leap(y) { 366 } else { 365 };
+    }
+    for m in 1..month {
+        days += days_in_month[(m - 1) as usize];
+        if m == 2 && is_leap(year) {
+            days += 1;
+        }
+    }
+    days += day.checked_sub(1)?;
+
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
 }
 
 #[test]
@@ -353,4 +842,215 @@ Allow: /?gws_rd=ssl$";
     assert!(parsed.is_ok());
     let (_, parsed) = parsed.unwrap();
     assert_eq!(parsed, result);
+
+    assert_eq!(
+        match_pattern("/?hl=*&gws_rd=ssl$", "/?hl=en&gws_rd=ssl"),
+        Some("/?hl=en&gws_rd=ssl".len())
+    );
+    assert_eq!(
+        match_pattern("/?hl=*&gws_rd=ssl$", "/?hl=en&gws_rd=ssl&foo=bar"),
+        None
+    );
+    assert_eq!(
+        match_pattern("/?hl=*&", "/?hl=en&gws_rd=ssl"),
+        Some("/?hl=en&".len())
+    );
+    assert_eq!(match_pattern("/?gws_rd=ssl$", "/?gws_rd=ssl"), Some(12));
+    assert_eq!(match_pattern("/?gws_rd=ssl$", "/?gws_rd=sslx"), None);
+}
+
+#[test]
+fn wildcard_matching() {
+    assert_eq!(match_pattern("/fish*.php", "/fish.php"), Some(9));
+    assert_eq!(
+        match_pattern("/fish*.php", "/fishheads/catfish.php?parameters"),
+        Some("/fishheads/catfish.php".len())
+    );
+    assert_eq!(match_pattern("/fish*.php", "/Fish.PHP"), None);
+    assert_eq!(
+        match_pattern("/*.php$", "/filename.php"),
+        Some("/filename.php".len())
+    );
+    assert_eq!(match_pattern("/*.php$", "/filename.php?parameters"), None);
+    assert_eq!(match_pattern("/*.php$", "/filename.php5"), None);
+    assert_eq!(match_pattern("*", "/anything/at/all"), Some(0));
+
+    // The final literal of an anchored pattern must align with the end of
+    // the path, not just occur somewhere in it.
+    assert_eq!(match_pattern("/*.php$", "/a.php.php"), Some("/a.php.php".len()));
+    assert_eq!(
+        match_pattern("/*.php$", "/foo.phpx.php"),
+        Some("/foo.phpx.php".len())
+    );
+    assert_eq!(match_pattern("/a*b$", "/aXbYb"), Some("/aXbYb".len()));
+
+    // A wildcard right before `$` makes the anchor a no-op, but the match
+    // still consumes through the end of the path (the wildcard swallows it).
+    assert_eq!(match_pattern("/foo*$", "/foo"), Some(4));
+    assert_eq!(match_pattern("/foo*$", "/foobar"), Some("/foobar".len()));
+    assert_eq!(match_pattern("/foo*$", "/fo"), None);
+}
+
+#[test]
+fn longest_match_wins_regardless_of_order() {
+    // `Allow: /org/` is listed before the more specific `Disallow`, so a
+    // first-match scan would wrongly allow `/org/plans.html`. The longest
+    // matching pattern must win instead.
+    let group = Robots::UserAgent(
+        "*".to_owned(),
+        vec![
+            Rule::Allow("/org/".to_owned()),
+            Rule::Disallow("/org/plans.html".to_owned()),
+        ],
+    );
+    assert!(!group.is_allowed("/org/plans.html"));
+    assert!(group.is_allowed("/org/about.html"));
+}
+
+#[test]
+fn equal_length_match_prefers_allow() {
+    let group = Robots::UserAgent(
+        "*".to_owned(),
+        vec![
+            Rule::Disallow("/page".to_owned()),
+            Rule::Allow("/page".to_owned()),
+        ],
+    );
+    assert!(group.is_allowed("/page"));
+}
+
+#[test]
+fn unmatched_path_defaults_to_allowed() {
+    let group = Robots::UserAgent("*".to_owned(), vec![Rule::Disallow("/private/".to_owned())]);
+    assert!(group.is_allowed("/public/"));
+}
+
+#[test]
+fn crawl_delay_rule() {
+    assert_eq!(
+        RobotsParser::parse_crawl_delay()("Crawl-delay: 1.5\r\n"),
+        Ok(("", Rule::CrawlDelay(1.5)))
+    );
+}
+
+#[test]
+fn request_rate_rule() {
+    assert_eq!(
+        RobotsParser::parse_request_rate()("Request-rate: 1/10\r\n"),
+        Ok((
+            "",
+            Rule::RequestRate(RequestRate {
+                requests: 1,
+                seconds: 10
+            })
+        ))
+    );
+}
+
+#[test]
+fn visit_time_rule() {
+    assert_eq!(
+        RobotsParser::parse_visit_time()("Visit-time: 0600-0845\r\n"),
+        Ok((
+            "",
+            Rule::VisitTime(VisitTime {
+                from: (6, 0),
+                to: (8, 45)
+            })
+        ))
+    );
+}
+
+#[test]
+fn politeness_directive_accessors() {
+    // A named group with no `*` fallback, so an unrelated agent genuinely
+    // has nothing to resolve (unlike `*`, which matches every agent).
+    let rules = "User-agent: specialbot\r
+Crawl-delay: 10\r
+Request-rate: 1/10\r
+Visit-time: 0600-0845\r
+Disallow: /private/";
+    let parsed = RobotsParser::parse(rules).unwrap();
+    assert_eq!(parsed.crawl_delay("specialbot"), Some(10.0));
+    assert_eq!(
+        parsed.request_rate("specialbot"),
+        Some(RequestRate {
+            requests: 1,
+            seconds: 10
+        })
+    );
+    assert_eq!(
+        parsed.visit_time("specialbot"),
+        Some(VisitTime {
+            from: (6, 0),
+            to: (8, 45)
+        })
+    );
+    assert_eq!(parsed.crawl_delay("other"), None);
+}
+
+#[test]
+fn sitemap_directive() {
+    let rules = "User-agent: *\r
+Disallow: /private/\r
+Sitemap: https://example.com/sitemap.xml\r
+Sitemap: not-a-valid-url\r
+Sitemap: https://example.com/sitemap2.xml";
+    let parsed = RobotsParser::parse(rules).unwrap();
+    assert_eq!(
+        parsed.sitemaps(),
+        vec![
+            &Url::parse("https://example.com/sitemap.xml").unwrap(),
+            &Url::parse("https://example.com/sitemap2.xml").unwrap(),
+        ]
+    );
+}
+
+#[test]
+fn parse_error_reports_offset_and_line() {
+    let rules = "User-agent: *\r\nDisallow: /\r\nnot a valid directive\r\n";
+    match RobotsParser::parse(rules) {
+        Err(RobotsError::Parse { offset, line }) => {
+            assert_eq!(offset, "User-agent: *\r\nDisallow: /\r\n".len());
+            assert_eq!(line, 3);
+        }
+        other => panic!("expected a Parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn percent_encoding_equivalence() {
+    // A decoded rule pattern matches an encoded request path, and vice versa.
+    assert_eq!(match_pattern("/~mak", "/%7Emak/mak.html"), Some(5));
+    assert_eq!(match_pattern("/%7Emak", "/~mak/mak.html"), Some(5));
+    assert_eq!(match_pattern("/~mak", "/~mak/mak.html"), Some(5));
+
+    // A reserved byte keeps whichever form it already had: a literal `&`
+    // never matches a pattern that spells it `%26`, or vice versa.
+    assert_eq!(match_pattern("/a&b", "/a%26b"), None);
+    assert_eq!(match_pattern("/a%26b", "/a&b"), None);
+    assert_eq!(match_pattern("/a&b", "/a&b"), Some(4));
+    assert_eq!(match_pattern("/a%26b", "/a%26b"), Some(6));
+}
+
+#[test]
+fn query_string_rule() {
+    let rules = "User-agent: *\r
+Disallow: /?hl=\r
+Allow: /search";
+    let parsed = RobotsParser::parse(rules).unwrap();
+    assert!(!parsed.can_fetch("*", "http://example.com/?hl=en"));
+    assert!(parsed.can_fetch("*", "http://example.com/search?q=flowers"));
+}
+
+#[cfg(feature = "web")]
+#[test]
+fn http_date_parsing() {
+    let parsed = parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT").unwrap();
+    assert_eq!(parsed, std::time::UNIX_EPOCH);
+
+    let parsed = parse_http_date("Thu, 01 Jan 1970 00:00:42 GMT").unwrap();
+    assert_eq!(parsed, std::time::UNIX_EPOCH + std::time::Duration::from_secs(42));
+
+    assert!(parse_http_date("not a date").is_none());
 }